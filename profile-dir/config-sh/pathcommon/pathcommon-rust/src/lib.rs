@@ -0,0 +1,376 @@
+// pathcommon_rs_ikluft: shared PATH-list assembly logic for pathmunge and pathfilter
+// by Ian Kluft
+// See https://github.com/ikluft/ikluft-tools/tree/master/profile-dir/config-sh
+//
+// Open Source licensing under terms of GNU General Public License version 3
+// SPDX identifier: GPL-3.0-only
+// https://opensource.org/licenses/GPL-3.0
+// https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use anyhow::{Context, Error, Result};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::{collections::HashSet, env, path::Path};
+
+// constants for the CLI shared by pathmunge and pathfilter
+pub const DEFAULT_VAR_NAME: &str = "PATH";
+pub const BEFORE_PARAM: &str = "before";
+pub const AFTER_PARAM: &str = "after";
+pub const VAR_PARAM: &str = "var";
+pub const DELIMITER_PARAM: &str = "delimiter";
+pub const EXCLUDE_PARAM: &str = "exclude";
+pub const FORMAT_PARAM: &str = "format";
+pub const FORMAT_VALUES: [&str; 7] = ["sh", "bash", "zsh", "csh", "tcsh", "fish", "nu"];
+
+// command line data shared by pathmunge and pathfilter
+pub struct CliOpts {
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub var_name: String,
+    // when None, the platform's native separator is used via std::env::split_paths/join_paths
+    pub delimiter: Option<String>,
+    pub exclude: Vec<String>,
+    // when None, the bare path string is printed instead of a shell assignment
+    pub format: Option<String>,
+}
+
+// build the before/after/var/delimiter/format argument parser shared by both tools;
+// set `with_exclude` to also add pathfilter's --exclude glob option
+pub fn build_command(name: &'static str, about: &'static str, with_exclude: bool) -> Command {
+    let cmd = Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new(BEFORE_PARAM)
+                .long(BEFORE_PARAM)
+                .value_name("PATH")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(AFTER_PARAM)
+                .long(AFTER_PARAM)
+                .value_name("PATH")
+                .num_args(1),
+        )
+        .group(
+            ArgGroup::new("position")
+                .args([BEFORE_PARAM, AFTER_PARAM])
+                .multiple(true)
+                .required(true),
+        )
+        .arg(
+            Arg::new(VAR_PARAM)
+                .long(VAR_PARAM)
+                .num_args(1)
+                .required(false)
+                .value_name("VARNAME")
+                .default_value(DEFAULT_VAR_NAME),
+        )
+        .arg(
+            Arg::new(DELIMITER_PARAM)
+                .long(DELIMITER_PARAM)
+                .num_args(1)
+                .required(false)
+                .value_name("DELIMITER")
+                .help("defaults to the platform's native PATH separator (':' on Unix, ';' on Windows)"),
+        )
+        .arg(
+            Arg::new(FORMAT_PARAM)
+                .long(FORMAT_PARAM)
+                .num_args(1)
+                .required(false)
+                .value_name("SHELL")
+                .value_parser(FORMAT_VALUES)
+                .help("print a ready-to-eval shell assignment instead of the bare path"),
+        );
+
+    if with_exclude {
+        cmd.arg(
+            Arg::new(EXCLUDE_PARAM)
+                .long(EXCLUDE_PARAM)
+                .value_name("GLOB")
+                .num_args(1)
+                .required(false)
+                .action(ArgAction::Append)
+                .help("drop path entries matching this glob pattern (repeatable)"),
+        )
+    } else {
+        cmd
+    }
+}
+
+// extract CliOpts from the matches returned by build_command()
+pub fn parse_cli_opts(matches: &ArgMatches) -> CliOpts {
+    CliOpts {
+        // extract CLI params as Options since these may be missing
+        before: matches.get_one::<String>(BEFORE_PARAM).cloned(),
+        after: matches.get_one::<String>(AFTER_PARAM).cloned(),
+
+        // extract and unwrap CLI params which won't be empty due to a default value
+        var_name: matches.get_one::<String>(VAR_PARAM).unwrap().to_owned(),
+
+        // None here means auto-detect the platform's native separator
+        delimiter: matches.get_one::<String>(DELIMITER_PARAM).cloned(),
+
+        // try_get_many(), not get_many(): pathmunge's Command never defines EXCLUDE_PARAM,
+        // and get_many() panics on an arg id the Command doesn't know about
+        exclude: matches
+            .try_get_many::<String>(EXCLUDE_PARAM)
+            .ok()
+            .flatten()
+            .map(|vals| vals.cloned().collect())
+            .unwrap_or_default(),
+
+        format: matches.get_one::<String>(FORMAT_PARAM).cloned(),
+    }
+}
+
+// quote a value for safe inclusion in a double-quoted shell string
+pub fn shell_quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "\\$")
+            .replace('`', "\\`")
+    )
+}
+
+// format a ready-to-eval shell assignment for the given variable and value
+pub fn format_export(var_name: &str, value: &str, shell: &str) -> String {
+    match shell {
+        "csh" | "tcsh" => format!("setenv {var_name} {}", shell_quote(value)),
+        "fish" => format!("set -gx {var_name} {}", shell_quote(value)),
+        "nu" => format!("$env.{var_name} = {}", shell_quote(value)),
+        _ => format!("export {var_name}={}", shell_quote(value)),
+    }
+}
+
+// PathBuilder assembles a PATH-like environment variable value from before/after
+// elements and the existing variable, with deduplication and optional canonicalization
+// and glob-based exclusion - the common machinery behind pathmunge and pathfilter
+pub struct PathBuilder {
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub var_name: String,
+    // when None, the platform's native separator is used via std::env::split_paths/join_paths
+    pub delimiter: Option<String>,
+    pub canonicalize: bool,
+    pub exclude: Vec<String>,
+}
+
+impl PathBuilder {
+    // construct a PathBuilder with defaults for everything but the variable name
+    pub fn new(var_name: impl Into<String>) -> Self {
+        PathBuilder {
+            before: None,
+            after: None,
+            var_name: var_name.into(),
+            delimiter: None,
+            canonicalize: false,
+            exclude: Vec::new(),
+        }
+    }
+
+    // assemble elements of path from before/after and the existing environment variable
+    fn elements(&self) -> Vec<String> {
+        let env_value = env::var(&self.var_name);
+
+        let mut elements: Vec<String> = Vec::new();
+        if let Some(before) = &self.before {
+            elements.push(before.to_owned());
+        }
+        if let Ok(env_value) = env_value {
+            elements.push(env_value);
+        }
+        if let Some(after) = &self.after {
+            elements.push(after.to_owned());
+        }
+        elements
+    }
+
+    // split a path-list element into directory strings, using the platform's native
+    // separator via std::env::split_paths when no explicit delimiter was set
+    fn split_element(&self, element: &str) -> Vec<String> {
+        match &self.delimiter {
+            Some(delimiter) => element.split(delimiter.as_str()).map(String::from).collect(),
+            None => env::split_paths(element)
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+        }
+    }
+
+    // join directory strings back into a path-list string, matching split_element's choice
+    fn join_elements(&self, path_out: &[String]) -> Result<String, Error> {
+        match &self.delimiter {
+            Some(delimiter) => Ok(path_out.join(delimiter.as_str())),
+            None => Ok(env::join_paths(path_out)
+                .context("failed to join path elements with the platform's native separator")?
+                .to_string_lossy()
+                .to_string()),
+        }
+    }
+
+    // compile the exclude glob patterns into a single glob set
+    fn build_exclude_set(&self) -> Result<GlobSet, Error> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.exclude {
+            builder.add(
+                Glob::new(pattern).with_context(|| format!("invalid glob pattern: {pattern}"))?,
+            );
+        }
+        builder.build().context("failed to build exclude glob set")
+    }
+
+    // assemble path directories into ordered set, skipping duplicates and invalid paths,
+    // and return the joined PATH-list string
+    pub fn build(&self) -> Result<String, Error> {
+        let exclude_set = self.build_exclude_set()?;
+
+        let mut path_out: Vec<String> = Vec::new();
+        let mut dirs_seen: HashSet<String> = HashSet::new();
+        for element in &self.elements() {
+            for dir_str in &self.split_element(element) {
+                let dir_path = Path::new(dir_str);
+
+                // canonicalize when requested, falling back to the raw string otherwise
+                let dir_key = if self.canonicalize {
+                    match dir_path.canonicalize() {
+                        Ok(x) => x.to_string_lossy().to_string(),
+                        Err(_) => continue,
+                    }
+                } else {
+                    dir_str.to_owned()
+                };
+
+                // skip entries matching --exclude, checking both the raw and canonical
+                // form so symlinked duplicates of an excluded directory are also caught
+                if exclude_set.is_match(dir_str.as_str()) || exclude_set.is_match(dir_key.as_str()) {
+                    continue;
+                }
+
+                // skip entries already seen
+                if dirs_seen.contains(&dir_key) {
+                    continue;
+                }
+
+                // skip paths that don't exist or aren't directories
+                if !dir_path.exists() || !dir_path.is_dir() {
+                    continue;
+                }
+
+                path_out.push(dir_key.clone());
+                dirs_seen.insert(dir_key);
+            }
+        }
+
+        self.join_elements(&path_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // create a unique temp dir for a test, under the OS temp dir
+    fn make_temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = env::temp_dir().join(format!("pathcommon_test_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn dedup_real_directories() {
+        let base = make_temp_dir("dedup");
+        let a = base.join("a");
+        fs::create_dir(&a).unwrap();
+
+        // the before element plus a duplicate of it via after should collapse to one entry
+        let builder = PathBuilder {
+            before: Some(a.to_string_lossy().to_string()),
+            after: Some(format!("{a}:{a}", a = a.to_string_lossy())),
+            var_name: "PATHCOMMON_TEST_DEDUP".to_string(),
+            delimiter: Some(":".to_string()),
+            canonicalize: true,
+            exclude: Vec::new(),
+        };
+        let result = builder.build().unwrap();
+        assert_eq!(result, a.canonicalize().unwrap().to_string_lossy());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinked_directory_dedups_with_canonicalize() {
+        use std::os::unix::fs::symlink;
+
+        let base = make_temp_dir("symlink");
+        let real = base.join("real");
+        let link = base.join("link");
+        fs::create_dir(&real).unwrap();
+        symlink(&real, &link).unwrap();
+
+        let builder = PathBuilder {
+            before: Some(real.to_string_lossy().to_string()),
+            after: Some(link.to_string_lossy().to_string()),
+            var_name: "PATHCOMMON_TEST_SYMLINK".to_string(),
+            delimiter: Some(":".to_string()),
+            canonicalize: true,
+            exclude: Vec::new(),
+        };
+        let result = builder.build().unwrap();
+        assert_eq!(result, real.canonicalize().unwrap().to_string_lossy());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn nonexistent_directory_is_dropped() {
+        let base = make_temp_dir("nonexistent");
+        let real = base.join("real");
+        let missing = base.join("does-not-exist");
+        fs::create_dir(&real).unwrap();
+
+        let builder = PathBuilder {
+            before: Some(missing.to_string_lossy().to_string()),
+            after: Some(real.to_string_lossy().to_string()),
+            var_name: "PATHCOMMON_TEST_MISSING".to_string(),
+            delimiter: Some(":".to_string()),
+            canonicalize: false,
+            exclude: Vec::new(),
+        };
+        let result = builder.build().unwrap();
+        assert_eq!(result, real.to_string_lossy());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn exclude_glob_drops_matching_directory() {
+        let base = make_temp_dir("exclude");
+        let keep = base.join("keep");
+        let drop = base.join("node_modules");
+        fs::create_dir(&keep).unwrap();
+        fs::create_dir(&drop).unwrap();
+
+        let builder = PathBuilder {
+            before: Some(format!(
+                "{}:{}",
+                keep.to_string_lossy(),
+                drop.to_string_lossy()
+            )),
+            after: None,
+            var_name: "PATHCOMMON_TEST_EXCLUDE".to_string(),
+            delimiter: Some(":".to_string()),
+            canonicalize: false,
+            exclude: vec!["*/node_modules".to_string()],
+        };
+        let result = builder.build().unwrap();
+        assert_eq!(result, keep.to_string_lossy());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
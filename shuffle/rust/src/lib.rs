@@ -9,72 +9,155 @@
 // https://www.gnu.org/licenses/gpl-3.0.en.html
 
 use anyhow::{bail, Context, Error, Result};
-use rand::{seq::SliceRandom, thread_rng};
+use clap::{Arg, Command};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use std::{
     env,
     fs::File,
     io,
     io::{BufRead, BufReader},
-    iter::Iterator,
     path::PathBuf,
 };
 
 // constants
-const MAX_FILE_SIZE: u64 = 1 << 16; // 64K max - arbitrary but in-memory algorithm is only for small files
+const FILE_PARAM: &str = "file";
+const SAMPLE_PARAM: &str = "sample-size";
+const SEED_PARAM: &str = "seed";
 
-// get file path from command line
-fn path_from_cli(mut args_iter: env::Args) -> Result<PathBuf, Error> {
-    // skip command name in position 0 of command line argument list
-    args_iter.next();
+// command line data
+struct CliOpts {
+    infile_path: Option<PathBuf>,
+    sample_size: Option<usize>,
+    seed: Option<u64>,
+}
+
+// process command line and return values
+fn process_cli(args_iter: env::Args) -> Result<CliOpts, Error> {
+    // command-line interface
+    let result = Command::new("shuffle")
+        .about("randomly shuffle lines of text from an input file or stdin")
+        .arg(Arg::new(FILE_PARAM).value_name("FILE").num_args(1))
+        .arg(
+            Arg::new(SAMPLE_PARAM)
+                .short('n')
+                .long(SAMPLE_PARAM)
+                .value_name("K")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new(SEED_PARAM)
+                .long(SEED_PARAM)
+                .value_name("N")
+                .num_args(1),
+        )
+        .try_get_matches_from(args_iter);
+    let matches = result?; // unwrap matches from result or return with CLI error
 
-    // get input file name from command line
-    let infile_param = args_iter.next().expect("file name parameter missing");
-    let infile_path = PathBuf::from(infile_param);
+    // extract values
+    let infile_path = matches.get_one::<String>(FILE_PARAM).map(PathBuf::from);
+    let sample_size = matches
+        .get_one::<String>(SAMPLE_PARAM)
+        .map(|s| s.parse::<usize>())
+        .transpose()
+        .context("invalid sample size for --sample-size")?;
+    let seed = matches
+        .get_one::<String>(SEED_PARAM)
+        .map(|s| s.parse::<u64>())
+        .transpose()
+        .context("invalid seed for --seed")?;
 
-    // basic file checks
-    if ! infile_path.exists() {
-        bail!("path does not exist: {}", infile_path.to_string_lossy());
+    // basic file checks, only applicable when a file was given - otherwise input is stdin
+    if let Some(ref infile_path) = infile_path {
+        if !infile_path.exists() {
+            bail!("path does not exist: {}", infile_path.to_string_lossy());
+        }
+        if !infile_path.is_file() {
+            bail!("path is not a regular file: {}", infile_path.to_string_lossy());
+        }
     }
-    if ! infile_path.is_file() {
-        bail!("path is not a regular file: {}", infile_path.to_string_lossy());
+
+    Ok(CliOpts {
+        infile_path,
+        sample_size,
+        seed,
+    })
+}
+
+// open the input file, or stdin when no file was given on the command line
+fn open_input(infile_path: &Option<PathBuf>) -> Result<Box<dyn BufRead>, Error> {
+    match infile_path {
+        Some(infile_path) => {
+            let infile = File::open(infile_path)
+                .with_context(|| format!("Failed to open {}", infile_path.to_string_lossy()))?;
+            Ok(Box::new(BufReader::new(infile)))
+        }
+        None => Ok(Box::new(BufReader::new(io::stdin()))),
     }
-    let infile_metadata = infile_path.metadata()?;
-    if infile_metadata.len() > MAX_FILE_SIZE {
-        bail!("file is too large for in-memory shuffle algorithm: {}", infile_path.to_string_lossy());
+}
+
+// read all lines of input into a vector - buffers the whole input in memory
+// a read error (e.g. invalid UTF-8) aborts with an error rather than silently
+// dropping or truncating lines, matching reservoir_sample's error handling
+fn read_all_lines(reader: Box<dyn BufRead>) -> Result<Vec<String>, Error> {
+    Ok(reader.lines().collect::<io::Result<Vec<String>>>()?)
+}
+
+// reservoir-sample K lines from a stream using Vitter's Algorithm R
+// memory stays at O(K) regardless of input length, so input size is unbounded
+fn reservoir_sample(
+    reader: Box<dyn BufRead>,
+    sample_size: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<String>, Error> {
+    let mut reservoir: Vec<String> = Vec::with_capacity(sample_size);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if i < sample_size {
+            reservoir.push(line);
+        } else {
+            let j = rng.gen_range(0..=i);
+            if j < sample_size {
+                reservoir[j] = line;
+            }
+        }
     }
 
-    // return path
-    Ok(infile_path)
+    // the reservoir holds an unordered sample - shuffle it once to randomize order
+    reservoir.shuffle(rng);
+    Ok(reservoir)
 }
 
-// read a file into a vector of strings
-fn read_file_lines(infile_path: &PathBuf) -> Result<Vec<String>, Error> {
-    let infile = File::open(infile_path).with_context(|| {
-        format!(
-            "Failed to open {}",
-            infile_path.to_string_lossy()
-        )
-    })?;
-    let reader = BufReader::new(infile);
-    Ok(reader
-        .lines()
-        .filter_map(io::Result::ok)
-        .collect::<Vec<String>>())
+// build a PRNG: seeded and reproducible when --seed was given, otherwise from entropy
+fn make_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
 }
 
 // run: library side of command line called from main()
 pub fn run(args_iter: env::Args) -> Result<(), Error> {
-    // get file path from command line
-    let infile_path = path_from_cli(args_iter)?;
+    // get input file path and options from command line
+    let cli = process_cli(args_iter)?;
+
+    // open input file, or stdin when no file was given
+    let reader = open_input(&cli.infile_path)?;
 
-    // read input file to vector
-    let mut lines = read_file_lines(&infile_path)?;
+    // build PRNG - seeded with --seed for reproducible output, otherwise from entropy
+    let mut rng = make_rng(cli.seed);
 
-    // shuffle vector
-    let mut rng = thread_rng();
-    lines.shuffle(&mut rng);
+    // shuffle or reservoir-sample the input, depending on whether -n was given
+    let lines = match cli.sample_size {
+        Some(sample_size) => reservoir_sample(reader, sample_size, &mut rng)?,
+        None => {
+            // no sample size requested: full shuffle requires buffering the whole input
+            let mut lines = read_all_lines(reader)?;
+            lines.shuffle(&mut rng);
+            lines
+        }
+    };
 
-    // print vector
+    // print lines
     for line in lines {
         println!("{}", line);
     }
@@ -83,3 +166,44 @@ pub fn run(args_iter: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // build a BufRead over a fixed set of lines, as if read from a file or stdin
+    fn reader_for(lines: &[&str]) -> Box<dyn BufRead> {
+        Box::new(Cursor::new(lines.join("\n").into_bytes()))
+    }
+
+    #[test]
+    fn reservoir_sample_is_deterministic_with_seed() {
+        let input = ["a", "b", "c", "d", "e"];
+
+        let mut rng = make_rng(Some(42));
+        let sample = reservoir_sample(reader_for(&input), 3, &mut rng).unwrap();
+
+        let mut same_seed_rng = make_rng(Some(42));
+        let repeat = reservoir_sample(reader_for(&input), 3, &mut same_seed_rng).unwrap();
+
+        assert_eq!(sample, repeat);
+    }
+
+    #[test]
+    fn reservoir_sample_exact_contents_for_fixed_seed() {
+        let input = ["a", "b", "c", "d", "e"];
+        let mut rng = make_rng(Some(42));
+        let sample = reservoir_sample(reader_for(&input), 3, &mut rng).unwrap();
+        assert_eq!(sample, vec!["d", "a", "b"]);
+    }
+
+    #[test]
+    fn read_all_lines_errors_on_invalid_utf8_instead_of_truncating() {
+        let mut bytes = b"one\ntwo\n".to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xFE]);
+        bytes.extend_from_slice(b"\nfour\n");
+        let reader: Box<dyn BufRead> = Box::new(Cursor::new(bytes));
+
+        assert!(read_all_lines(reader).is_err());
+    }
+}